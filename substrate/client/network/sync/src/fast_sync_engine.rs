@@ -19,10 +19,20 @@
 //! `SyncingEngine` is the actor responsible for syncing Substrate chain
 //! to tip and keep the blockchain up to date with network updates.
 
+mod bootstrap;
+mod checkpoint;
+mod metrics;
 mod syncing_service;
+mod warp;
 
 use crate::{
-	fast_sync_engine::syncing_service::{SyncingService, ToServiceCommand},
+	fast_sync_engine::{
+		bootstrap::{Bootstrapper, JustificationVerifier},
+		checkpoint::{Checkpoint, CheckpointStore},
+		metrics::Metrics,
+		syncing_service::{SyncingService, ToServiceCommand},
+		warp::{VerifiedWarpProof, WarpProofRequest, WarpProofVerifier},
+	},
 	pending_responses::{PendingResponses, ResponseEvent},
 	schema::v1::{StateRequest, StateResponse},
 	service::{self},
@@ -31,12 +41,18 @@ use crate::{
 	LOG_TARGET,
 };
 
+use delay_map::HashMapDelay;
 use futures::{channel::oneshot, FutureExt, StreamExt};
 use libp2p::{request_response::OutboundFailure, PeerId};
 use log::{debug, error, trace};
 use prost::Message;
 
-use crate::{state_request_handler::generate_protocol_name, strategy::state::StateStrategyAction};
+use crate::{
+	state_request_handler::generate_protocol_name as generate_state_protocol_name,
+	strategy::state::StateStrategyAction,
+	warp_request_handler::generate_protocol_name as generate_warp_protocol_name,
+};
+use codec::Encode as _;
 use sc_client_api::{BlockBackend, ProofProvider};
 use sc_consensus::{import_queue::ImportQueueService, IncomingBlock};
 use sc_network::{
@@ -47,12 +63,21 @@ use sc_network::{
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver};
 use sp_blockchain::Error as ClientError;
 use sp_runtime::{
-	traits::{Block as BlockT, NumberFor, Zero},
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, Zero},
 	Justifications,
 };
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use substrate_prometheus_endpoint::Registry;
 use tokio::sync::Mutex;
 
+/// How long we wait for a state request to be answered before giving up on the peer ourselves,
+/// rather than relying solely on libp2p's own `OutboundFailure::Timeout`.
+const STATE_REQUEST_TIMEOUT: Duration = Duration::from_secs(40);
+
 /// Peer information
 #[derive(Clone, Debug)]
 pub struct Peer<B: BlockT> {
@@ -71,14 +96,37 @@ mod rep {
 	pub const REFUSED: Rep = Rep::new(-(1 << 10), "Request refused");
 	/// Reputation change when a peer doesn't respond in time to our messages.
 	pub const TIMEOUT: Rep = Rep::new(-(1 << 10), "Request timeout");
+	/// Reputation change when our own request deadline elapses before libp2p reports a
+	/// timeout. Milder than [`TIMEOUT`] since the peer may still be honest, just slow.
+	pub const LOCAL_TIMEOUT: Rep = Rep::new(-(1 << 8), "Local request deadline elapsed");
+}
+
+/// Lazily builds the [`StateStrategy`] once a warp phase has established the target header.
+type DeferredStrategyBuilder<B> = Box<dyn FnOnce(<B as BlockT>::Header) -> StateStrategy<B> + Send>;
+
+/// Which phase of the sync the engine is currently driving.
+enum Phase<B: BlockT> {
+	/// Downloading and verifying warp proofs to establish the finalized target header.
+	Warp {
+		verifier: Box<dyn WarpProofVerifier<B>>,
+		/// Request for the next proof to fetch, advanced as proofs are verified.
+		next_request: WarpProofRequest<B>,
+		/// Builds the state-download phase once the target header is known.
+		build_state_strategy: DeferredStrategyBuilder<B>,
+	},
+	/// Downloading state for the (now known) target header.
+	State(StateStrategy<B>),
+	/// Transient placeholder used only while swapping out of [`Phase::Warp`]; never observed
+	/// outside of `process_response_event`.
+	Transitioning,
 }
 
 pub struct FastSyncingEngine<B: BlockT, IQS>
 where
 	IQS: ImportQueueService<B> + ?Sized,
 {
-	/// Syncing strategy.
-	strategy: StateStrategy<B>,
+	/// Syncing strategy, optionally preceded by a warp-proof phase.
+	strategy: Phase<B>,
 
 	/// Network service.
 	network_service: service::network::NetworkServiceHandle,
@@ -98,13 +146,53 @@ where
 	/// Pending responses
 	pending_responses: PendingResponses<B>,
 
+	/// Local deadlines for outstanding state requests, keyed by the peer they were sent to.
+	///
+	/// A peer only ever has one state request in flight at a time, so the peer ID alone is
+	/// enough to correlate a timer with its request. This lets us abandon a sluggish peer on
+	/// our own schedule and retry elsewhere, instead of waiting for libp2p's own
+	/// `OutboundFailure::Timeout` to eventually fire.
+	request_timeouts: HashMapDelay<PeerId, OpaqueStateRequest>,
+
+	/// When each currently outstanding state request was sent, used to compute the
+	/// `request_duration` metric once the matching `ResponseEvent` arrives.
+	request_started: HashMap<PeerId, Instant>,
+
 	/// Protocol name used to send out state requests
 	state_request_protocol_name: ProtocolName,
 
+	/// Protocol name used to send out warp proof requests, if the engine was started with a
+	/// warp phase.
+	warp_request_protocol_name: Option<ProtocolName>,
+
+	/// Whether a warp proof request is currently outstanding.
+	warp_in_flight: bool,
+
+	/// Header sync is targeting, once known. `None` while a warp phase hasn't yet established
+	/// it. Kept around purely so completed key ranges can be checkpointed against it.
+	target_header: Option<B::Header>,
+
+	/// Where completed state key ranges are checkpointed, if the engine was given a store.
+	checkpoint_store: Option<Arc<dyn CheckpointStore<B>>>,
+
+	/// Last key of a response [`StateStrategy::on_state_response`] has been handed but not yet
+	/// confirmed accepted, keyed by the peer it came from.
+	///
+	/// `on_state_response` doesn't report synchronously whether it accepted or rejected a
+	/// response; a rejection only surfaces later as a `StateStrategyAction::DropPeer` from the
+	/// very next `actions()` poll. So a response's last key is staged here instead of
+	/// checkpointed immediately, and only committed once that poll comes back without dropping
+	/// the peer it came from - otherwise a bad response could get checkpointed as progress before
+	/// the strategy ever rejects it.
+	pending_checkpoints: HashMap<PeerId, Vec<u8>>,
+
 	/// Handle to import queue.
 	import_queue: Arc<Mutex<Box<IQS>>>,
 
 	last_block: Option<IncomingBlock<B>>,
+
+	/// Prometheus metrics, if a registry was supplied when the engine was created.
+	metrics: Option<Metrics>,
 }
 
 impl<B: BlockT, IQS> FastSyncingEngine<B, IQS>
@@ -112,6 +200,17 @@ where
 	B: BlockT,
 	IQS: ImportQueueService<B> + ?Sized + 'static,
 {
+	/// Create a new [`FastSyncingEngine`].
+	///
+	/// `sync_peers` seeds the strategy with every peer eligible to serve state requests, rather
+	/// than the single fixed peer this constructor used to require. `process_strategy_actions`
+	/// dispatches every `SendStateRequest` action [`StateStrategy`] yields in a tick, so whether
+	/// more than one request ends up outstanding at once, and how the target key space is
+	/// partitioned across peers, is entirely up to [`StateStrategy`]'s own implementation - this
+	/// constructor only widens the pool it has to draw from. The one thing enforced on this side
+	/// regardless of what the strategy does is backpressure against double-dispatch: a
+	/// `SendStateRequest` for a peer that already has a request in flight is dropped rather than
+	/// sent, since `request_timeouts`/`request_started` only track one deadline per peer.
 	pub fn new<Client: BlockBackend<B> + ProofProvider<B> + Send + Sync + 'static>(
 		client: Arc<Client>,
 		import_queue: Arc<Mutex<Box<IQS>>>,
@@ -121,23 +220,38 @@ where
 		target_body: Option<Vec<B::Extrinsic>>,
 		target_justifications: Option<Justifications>,
 		skip_proof: bool,
-		current_sync_peer: (PeerId, NumberFor<B>),
+		sync_peers: Vec<(PeerId, NumberFor<B>)>,
+		metrics_registry: Option<&Registry>,
+		checkpoint_store: Option<Arc<dyn CheckpointStore<B>>>,
 	) -> Result<(Self, SyncingService<B>), ClientError> {
+		if sync_peers.is_empty() {
+			return Err(ClientError::Backend("At least one sync peer is required".into()))
+		}
+
+		let metrics = metrics_registry
+			.map(Metrics::register)
+			.transpose()
+			.map_err(|e| ClientError::Backend(format!("Failed to register fast sync metrics: {e}")))?;
+
 		let genesis_hash = client
 			.block_hash(Zero::zero())
 			.ok()
 			.flatten()
 			.expect("Genesis block exists; qed");
-		let state_request_protocol_name = generate_protocol_name(genesis_hash, fork_id).into();
+		let state_request_protocol_name = generate_state_protocol_name(genesis_hash, fork_id).into();
 
-		// Initialize syncing strategy.
-		let strategy = StateStrategy::new(
-			client.clone(),
+		let target_header_for_checkpoint = target_header.clone();
+		// Initialize syncing strategy, handing it the full peer pool instead of a single peer.
+		// If a checkpoint for this same target already exists, resume from the first missing
+		// range instead of the genesis key.
+		let strategy = Self::build_state_strategy(
+			&checkpoint_store,
+			client,
 			target_header,
 			target_body,
 			target_justifications,
 			skip_proof,
-			vec![current_sync_peer].into_iter(),
+			sync_peers,
 		);
 
 		let (tx, service_rx) = tracing_unbounded("mpsc_chain_sync", 100_000);
@@ -145,28 +259,225 @@ where
 		Ok((
 			Self {
 				import_queue,
-				strategy,
+				strategy: Phase::State(strategy),
 				network_service,
 				peers: HashMap::new(),
 				service_rx,
 				syncing_started: None,
 				pending_responses: PendingResponses::new(),
+				request_timeouts: HashMapDelay::new(STATE_REQUEST_TIMEOUT),
+				request_started: HashMap::new(),
 				state_request_protocol_name,
+				warp_request_protocol_name: None,
+				warp_in_flight: false,
+				target_header: Some(target_header_for_checkpoint),
+				checkpoint_store,
+				pending_checkpoints: HashMap::new(),
 				last_block: None,
+				metrics,
 			},
 			SyncingService::new(tx),
 		))
 	}
 
+	/// Build a [`StateStrategy`], resuming from a saved checkpoint when one exists for
+	/// `target_header` rather than starting from the genesis key.
+	fn build_state_strategy<Client: BlockBackend<B> + ProofProvider<B> + Send + Sync + 'static>(
+		checkpoint_store: &Option<Arc<dyn CheckpointStore<B>>>,
+		client: Arc<Client>,
+		target_header: B::Header,
+		target_body: Option<Vec<B::Extrinsic>>,
+		target_justifications: Option<Justifications>,
+		skip_proof: bool,
+		sync_peers: Vec<(PeerId, NumberFor<B>)>,
+	) -> StateStrategy<B> {
+		let resume_from = checkpoint_store.as_ref().and_then(|store| match store.load() {
+			Ok(Some(checkpoint)) if checkpoint.target_header == target_header => {
+				Some(checkpoint.last_key)
+			},
+			Ok(_) => None,
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "Failed to load fast sync checkpoint: {e:?}");
+				None
+			},
+		});
+
+		match resume_from {
+			Some(start_key) => StateStrategy::new_with_start_key(
+				client,
+				target_header,
+				target_body,
+				target_justifications,
+				skip_proof,
+				sync_peers.into_iter(),
+				start_key,
+			),
+			None => StateStrategy::new(
+				client,
+				target_header,
+				target_body,
+				target_justifications,
+				skip_proof,
+				sync_peers.into_iter(),
+			),
+		}
+	}
+
+	/// Create a new [`FastSyncingEngine`] that runs a warp-proof phase first to establish its
+	/// own `target_header`, instead of requiring the caller to already know it.
+	///
+	/// `warp_begin` is the block the first warp proof should start from (typically the local
+	/// genesis hash); `warp_verifier` checks each downloaded proof against the locally known
+	/// authority set and reports the finalized header it establishes. Once the chain tip is
+	/// reached the engine transitions into the same state-download phase `Self::new` would have
+	/// started with that header as `target_header`, resuming from `checkpoint_store` exactly as
+	/// `Self::new` does if one was saved for that same header.
+	#[allow(clippy::too_many_arguments)]
+	pub fn new_with_warp_sync<Client: BlockBackend<B> + ProofProvider<B> + Send + Sync + 'static>(
+		client: Arc<Client>,
+		import_queue: Arc<Mutex<Box<IQS>>>,
+		network_service: service::network::NetworkServiceHandle,
+		fork_id: Option<&str>,
+		warp_begin: B::Hash,
+		warp_verifier: impl WarpProofVerifier<B> + 'static,
+		target_body: Option<Vec<B::Extrinsic>>,
+		target_justifications: Option<Justifications>,
+		skip_proof: bool,
+		sync_peers: Vec<(PeerId, NumberFor<B>)>,
+		metrics_registry: Option<&Registry>,
+		checkpoint_store: Option<Arc<dyn CheckpointStore<B>>>,
+	) -> Result<(Self, SyncingService<B>), ClientError> {
+		if sync_peers.is_empty() {
+			return Err(ClientError::Backend("At least one sync peer is required".into()))
+		}
+
+		let metrics = metrics_registry.map(Metrics::register).transpose().map_err(|e| {
+			ClientError::Backend(format!("Failed to register fast sync metrics: {e}"))
+		})?;
+
+		let genesis_hash = client
+			.block_hash(Zero::zero())
+			.ok()
+			.flatten()
+			.expect("Genesis block exists; qed");
+		let state_request_protocol_name = generate_state_protocol_name(genesis_hash, fork_id).into();
+		let warp_request_protocol_name = generate_warp_protocol_name(genesis_hash, fork_id).into();
+
+		let deferred_checkpoint_store = checkpoint_store.clone();
+		let build_state_strategy: DeferredStrategyBuilder<B> = Box::new(move |target_header| {
+			Self::build_state_strategy(
+				&deferred_checkpoint_store,
+				client,
+				target_header,
+				target_body,
+				target_justifications,
+				skip_proof,
+				sync_peers,
+			)
+		});
+
+		let phase = Phase::Warp {
+			verifier: Box::new(warp_verifier),
+			next_request: WarpProofRequest { begin: warp_begin },
+			build_state_strategy,
+		};
+
+		let (tx, service_rx) = tracing_unbounded("mpsc_chain_sync", 100_000);
+
+		Ok((
+			Self {
+				import_queue,
+				strategy: phase,
+				network_service,
+				peers: HashMap::new(),
+				service_rx,
+				syncing_started: None,
+				pending_responses: PendingResponses::new(),
+				request_timeouts: HashMapDelay::new(STATE_REQUEST_TIMEOUT),
+				request_started: HashMap::new(),
+				state_request_protocol_name,
+				warp_request_protocol_name: Some(warp_request_protocol_name),
+				warp_in_flight: false,
+				last_block: None,
+				metrics,
+				target_header: None,
+				checkpoint_store,
+				pending_checkpoints: HashMap::new(),
+			},
+			SyncingService::new(tx),
+		))
+	}
+
+	/// Create a new [`FastSyncingEngine`] whose sync target is discovered from a trusted
+	/// node's HTTP endpoint rather than supplied by the caller.
+	///
+	/// `bootstrapper` fetches the remote node's latest finalized checkpoint; `verifier`
+	/// checks the accompanying justification against a known authority set. The peers the
+	/// bootstrap node advertises become the initial sync peer pool, so the engine never needs
+	/// a manually provided peer.
+	pub async fn new_with_http_bootstrap<
+		Client: BlockBackend<B> + ProofProvider<B> + Send + Sync + 'static,
+	>(
+		client: Arc<Client>,
+		import_queue: Arc<Mutex<Box<IQS>>>,
+		network_service: service::network::NetworkServiceHandle,
+		fork_id: Option<&str>,
+		bootstrapper: Bootstrapper,
+		verifier: &dyn JustificationVerifier<B>,
+		skip_proof: bool,
+		with_body: bool,
+		metrics_registry: Option<&Registry>,
+		checkpoint_store: Option<Arc<dyn CheckpointStore<B>>>,
+	) -> Result<(Self, SyncingService<B>), ClientError> {
+		let checkpoint =
+			bootstrapper.fetch_checkpoint::<B>(with_body, verifier).await.map_err(|e| {
+				error!(target: LOG_TARGET, "Failed to bootstrap fast sync target: {e:?}");
+				e
+			})?;
+
+		let sync_peers = checkpoint
+			.peers
+			.into_iter()
+			.map(|peer_id| (peer_id, *checkpoint.header.number()))
+			.collect::<Vec<_>>();
+
+		Self::new(
+			client,
+			import_queue,
+			network_service,
+			fork_id,
+			checkpoint.header,
+			checkpoint.body,
+			Some(checkpoint.justifications),
+			skip_proof,
+			sync_peers,
+			metrics_registry,
+			checkpoint_store,
+		)
+	}
+
 	pub async fn run(mut self) -> Result<Option<IncomingBlock<B>>, ClientError> {
 		self.syncing_started = Some(Instant::now());
 
 		loop {
+			if let Some(metrics) = &self.metrics {
+				metrics.sync_duration_seconds.set(
+					self.syncing_started
+						.map(|started| started.elapsed().as_secs())
+						.unwrap_or_default(),
+				);
+			}
+
 			tokio::select! {
 				command = self.service_rx.select_next_some() =>
 					self.process_service_command(command),
 				response_event = self.pending_responses.select_next_some() =>
 					self.process_response_event(response_event),
+				timed_out = self.request_timeouts.next() => {
+					if let Some(Ok((peer_id, request))) = timed_out {
+						self.process_request_timeout(peer_id, request);
+					}
+				},
 			}
 
 			// Process actions requested by a syncing strategy.
@@ -189,21 +500,53 @@ where
 	}
 
 	async fn process_strategy_actions(&mut self) -> Result<Option<()>, ClientError> {
-		let actions = self.strategy.actions().collect::<Vec<_>>();
+		// The warp phase isn't driven by a `StateStrategyAction` stream; we simply keep one
+		// proof request outstanding until `process_response_event` transitions us into
+		// `Phase::State`.
+		let Phase::State(_) = &self.strategy else {
+			self.ensure_warp_request_in_flight();
+			return Ok(Some(()))
+		};
+
+		let Phase::State(strategy) = &mut self.strategy else { unreachable!() };
+		let actions = strategy.actions().collect::<Vec<_>>();
 		if actions.is_empty() {
 			return Err(ClientError::Backend("Fast sync failed - no further actions.".into()))
 		}
 
+		let mut dropped_peers = Vec::new();
+
 		for action in actions.into_iter() {
 			match action {
-				StateStrategyAction::SendStateRequest { peer_id, request } => {
-					self.send_state_request(peer_id, request);
-				},
+				// Dispatches every `SendStateRequest` action yielded this tick, so whatever
+				// concurrency and key-range partitioning [`StateStrategy`] decides on is
+				// reflected here with no extra bookkeeping on our side - except for the one
+				// invariant this engine itself relies on: `request_timeouts`/`request_started`
+				// are keyed by peer, so two requests in flight to the same peer at once would
+				// silently clobber each other's deadline. Guard against that regardless of what
+				// the strategy does or doesn't enforce on its end.
+				StateStrategyAction::SendStateRequest { peer_id, request } =>
+					if self.request_timeouts.contains_key(&peer_id) {
+						debug!(
+							target: LOG_TARGET,
+							"Dropping SendStateRequest for {peer_id:?}: a state request is \
+								already in flight to it.",
+						);
+					} else {
+						self.send_state_request(peer_id, request);
+					},
 				StateStrategyAction::DropPeer(BadPeer(peer_id, rep)) => {
 					self.pending_responses.remove(&peer_id);
+					self.request_timeouts.remove(&peer_id);
+					self.request_started.remove(&peer_id);
 					self.network_service
 						.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
 					self.network_service.report_peer(peer_id, rep);
+					dropped_peers.push(peer_id);
+
+					if let Some(metrics) = &self.metrics {
+						metrics.peers_dropped.inc();
+					}
 
 					trace!(target: LOG_TARGET, "{peer_id:?} dropped: {rep:?}.");
 				},
@@ -211,6 +554,15 @@ where
 					self.last_block = blocks.first().cloned();
 					self.import_queue.lock().await.import_blocks(origin, blocks);
 
+					if let Some(store) = &self.checkpoint_store {
+						if let Err(e) = store.clear() {
+							log::warn!(
+								target: LOG_TARGET,
+								"Failed to clear fast sync checkpoint: {e:?}",
+							);
+						}
+					}
+
 					return Ok(None)
 				},
 				StateStrategyAction::Finished => {
@@ -219,14 +571,24 @@ where
 			}
 		}
 
+		self.commit_staged_checkpoints(&dropped_peers);
+
 		Ok(Some(()))
 	}
 
 	fn process_service_command(&mut self, command: ToServiceCommand<B>) {
 		match command {
 			ToServiceCommand::Status(tx) => {
-				let mut status = self.strategy.status();
+				let mut status = match &self.strategy {
+					Phase::State(strategy) => strategy.status(),
+					Phase::Warp { .. } | Phase::Transitioning => Default::default(),
+				};
 				status.num_connected_peers = self.peers.len() as u32;
+
+				if let Some(metrics) = &self.metrics {
+					metrics.connected_peers.set(status.num_connected_peers as u64);
+				}
+
 				let _ = tx.send(status);
 			},
 			ToServiceCommand::PeersInfo(tx) => {
@@ -257,6 +619,8 @@ where
 					tx,
 					IfDisconnected::ImmediateError,
 				);
+				self.request_timeouts.insert(peer_id, request);
+				self.request_started.insert(peer_id, Instant::now());
 			},
 			Err(err) => {
 				log::warn!(
@@ -267,6 +631,81 @@ where
 		}
 	}
 
+	/// Make sure exactly one warp proof request is outstanding while we're in [`Phase::Warp`].
+	fn ensure_warp_request_in_flight(&mut self) {
+		if self.warp_in_flight {
+			return
+		}
+
+		let Phase::Warp { next_request, .. } = &self.strategy else { return };
+		let request = next_request.clone();
+
+		let Some(&peer_id) = self.peers.keys().next() else {
+			trace!(target: LOG_TARGET, "No peer available yet to request a warp proof from.");
+			return
+		};
+
+		self.send_warp_request(peer_id, request);
+	}
+
+	fn send_warp_request(&mut self, peer_id: PeerId, request: WarpProofRequest<B>) {
+		let (tx, rx) = oneshot::channel();
+
+		self.pending_responses.insert(peer_id, PeerRequest::WarpProof, rx.boxed());
+
+		self.network_service.start_request(
+			peer_id,
+			self.warp_request_protocol_name
+				.clone()
+				.expect("only called while `warp_request_protocol_name` is set; qed"),
+			request.begin.encode(),
+			tx,
+			IfDisconnected::ImmediateError,
+		);
+		self.warp_in_flight = true;
+	}
+
+	/// Called when our own deadline for an outstanding state request elapses before libp2p
+	/// reports a result either way.
+	fn process_request_timeout(&mut self, peer_id: PeerId, request: OpaqueStateRequest) {
+		// The response (or a libp2p-level failure) may have arrived and already removed this
+		// entry from `pending_responses` in the same tick our timer fired; whichever of the two
+		// gets here first is the one that acts.
+		if self.pending_responses.remove(&peer_id).is_none() {
+			return
+		}
+		self.request_started.remove(&peer_id);
+
+		debug!(
+			target: LOG_TARGET,
+			"Local deadline elapsed for state request to {peer_id:?}, retrying with another peer.",
+		);
+
+		self.network_service.report_peer(peer_id, rep::LOCAL_TIMEOUT);
+		self.network_service
+			.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
+		if let Some(metrics) = &self.metrics {
+			metrics.timeout.inc();
+		}
+
+		let Some(next_peer) = self.best_alternative_peer(peer_id) else {
+			debug!(
+				target: LOG_TARGET,
+				"No alternative peer available to retry timed out state request.",
+			);
+			return
+		};
+
+		self.send_state_request(next_peer, request);
+	}
+
+	/// Pick the best peer to retry a state request on, excluding `exclude` (the peer that just
+	/// timed out) and any peer with a state request already in flight, preferring the one that
+	/// has announced the highest best block.
+	fn best_alternative_peer(&self, exclude: PeerId) -> Option<PeerId> {
+		pick_best_peer(&self.peers, exclude, |peer_id| self.request_timeouts.contains_key(peer_id))
+	}
+
 	fn encode_state_request(request: &OpaqueStateRequest) -> Result<Vec<u8>, String> {
 		let request: &StateRequest = request.0.downcast_ref().ok_or_else(|| {
 			"Failed to downcast opaque state response during encoding, this is an \
@@ -277,24 +716,90 @@ where
 		Ok(request.encode_to_vec())
 	}
 
-	fn decode_state_response(response: &[u8]) -> Result<OpaqueStateResponse, String> {
+	/// Decode a raw state response, also returning the number of key-value pairs it carries
+	/// (so callers can feed the `key_values_imported` metric without re-decoding) and the last
+	/// key it covered, if any (so callers can checkpoint progress without re-decoding either).
+	fn decode_state_response(
+		response: &[u8],
+	) -> Result<(OpaqueStateResponse, usize, Option<Vec<u8>>), String> {
 		let response = StateResponse::decode(response)
 			.map_err(|error| format!("Failed to decode state response: {error}"))?;
 
-		Ok(OpaqueStateResponse(Box::new(response)))
+		let key_values = response.key_value_state.iter().map(|kv| kv.entries.len()).sum();
+		let last_key = response
+			.key_value_state
+			.last()
+			.and_then(|kv| kv.entries.last())
+			.map(|entry| entry.key.clone());
+
+		Ok((OpaqueStateResponse(Box::new(response)), key_values, last_key))
+	}
+
+	/// Stage `last_key`, the last key a `StateResponse` batch covered, to be checkpointed once
+	/// [`Self::commit_staged_checkpoints`] confirms the strategy didn't reject it.
+	///
+	/// `StateStrategy::on_state_response` doesn't report acceptance synchronously - a bad or
+	/// unverifiable response is only rejected later, via a `StateStrategyAction::DropPeer` from
+	/// the next `actions()` poll - so `last_key` can't be persisted as completed progress right
+	/// here; it's only staged, keyed by the peer it came from, so it's on hand if that poll comes
+	/// back clean. A no-op unless the engine was given a `checkpoint_store`.
+	///
+	/// Deliberately keyed off the *response's* last key, not the `start` key of the request that
+	/// produced it: checkpointing the request's `start` would make a resumed sync re-request the
+	/// chunk that had just completed, since `start` is where that chunk began, not where it
+	/// ended.
+	fn stage_checkpoint(&mut self, peer_id: PeerId, last_key: Option<Vec<u8>>) {
+		let (Some(_), Some(last_key)) = (&self.checkpoint_store, last_key) else { return };
+		self.pending_checkpoints.insert(peer_id, last_key);
+	}
+
+	/// Commit every staged checkpoint that `dropped_peers` didn't just reject, then clear the
+	/// stage. Called once per tick after a `Phase::State` strategy's `actions()` have all been
+	/// processed, so any `StateStrategyAction::DropPeer` triggered by the response that staged a
+	/// checkpoint has had a chance to cancel it first.
+	fn commit_staged_checkpoints(&mut self, dropped_peers: &[PeerId]) {
+		for peer_id in dropped_peers {
+			self.pending_checkpoints.remove(peer_id);
+		}
+
+		let (Some(store), Some(target_header)) = (&self.checkpoint_store, &self.target_header)
+		else {
+			self.pending_checkpoints.clear();
+			return
+		};
+
+		for last_key in self.pending_checkpoints.values() {
+			let checkpoint = Checkpoint { target_header: target_header.clone(), last_key: last_key.clone() };
+			if let Err(e) = store.save(&checkpoint) {
+				log::warn!(target: LOG_TARGET, "Failed to persist fast sync checkpoint: {e:?}");
+			}
+		}
+		self.pending_checkpoints.clear();
 	}
 
 	fn process_response_event(&mut self, response_event: ResponseEvent<B>) {
 		let ResponseEvent { peer_id, request, response } = response_event;
 
+		// The request resolved through the normal libp2p path, so cancel our own deadline for
+		// it; otherwise `process_request_timeout` would fire later for a request that's no
+		// longer outstanding.
+		self.request_timeouts.remove(&peer_id);
+
+		if let Some(started) = self.request_started.remove(&peer_id) {
+			if let Some(metrics) = &self.metrics {
+				metrics.request_duration.observe(started.elapsed().as_secs_f64());
+			}
+		}
+
 		match response {
 			Ok(Ok((resp, _))) => match request {
 				PeerRequest::Block(req) => {
 					error!("Unexpected PeerRequest::Block - {:?}", req);
 				},
 				PeerRequest::State => {
-					let response = match Self::decode_state_response(&resp[..]) {
-						Ok(proto) => proto,
+					let (response, key_values, last_key) = match Self::decode_state_response(&resp[..])
+					{
+						Ok(decoded) => decoded,
 						Err(e) => {
 							debug!(
 								target: LOG_TARGET,
@@ -303,43 +808,122 @@ where
 							self.network_service.report_peer(peer_id, rep::BAD_MESSAGE);
 							self.network_service
 								.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
+							if let Some(metrics) = &self.metrics {
+								metrics.bad_message.inc();
+							}
 							return
 						},
 					};
 
-					self.strategy.on_state_response(peer_id, response);
+					if let Some(metrics) = &self.metrics {
+						metrics.state_responses_received.inc();
+						metrics.bytes_imported.inc_by(resp.len() as u64);
+						metrics.key_values_imported.inc_by(key_values as u64);
+					}
+
+					match &mut self.strategy {
+						Phase::State(strategy) => {
+							strategy.on_state_response(peer_id, response);
+							self.stage_checkpoint(peer_id, last_key);
+						},
+						Phase::Warp { .. } | Phase::Transitioning => {
+							error!(
+								target: LOG_TARGET,
+								"Received a state response from {peer_id:?} while still in the \
+									warp phase.",
+							);
+						},
+					}
 				},
 				PeerRequest::WarpProof => {
-					error!("Unexpected PeerRequest::WarpProof",);
+					self.warp_in_flight = false;
+
+					let Phase::Warp { verifier, .. } = &self.strategy else {
+						error!(
+							target: LOG_TARGET,
+							"Received a warp proof response from {peer_id:?} while not in the \
+								warp phase.",
+						);
+						return
+					};
+
+					match verifier.verify(&resp) {
+						Ok(VerifiedWarpProof { header, is_finished }) => {
+							if is_finished {
+								trace!(
+									target: LOG_TARGET,
+									"Warp phase complete, target header established at \
+										{header:?}; switching to state sync.",
+								);
+
+								self.target_header = Some(header.clone());
+
+								let Phase::Warp { build_state_strategy, .. } =
+									std::mem::replace(&mut self.strategy, Phase::Transitioning)
+								else {
+									unreachable!("matched on Phase::Warp above; qed")
+								};
+								self.strategy = Phase::State(build_state_strategy(header));
+							} else {
+								let Phase::Warp { next_request, .. } = &mut self.strategy else {
+									unreachable!("matched on Phase::Warp above; qed")
+								};
+								*next_request = WarpProofRequest { begin: header.hash() };
+							}
+						},
+						Err(e) => {
+							debug!(
+								target: LOG_TARGET,
+								"Peer {peer_id:?} sent a malformed warp proof: {e:?}.",
+							);
+							self.network_service.report_peer(peer_id, rep::BAD_MESSAGE);
+							self.network_service.disconnect_peer(
+								peer_id,
+								self.warp_request_protocol_name
+									.clone()
+									.expect("only reached while in the warp phase; qed"),
+							);
+							if let Some(metrics) = &self.metrics {
+								metrics.bad_message.inc();
+							}
+						},
+					}
 				},
 			},
 			Ok(Err(e)) => {
 				debug!(target: LOG_TARGET, "Request to peer {peer_id:?} failed: {e:?}.");
 
+				self.on_request_failed(&request);
+				let protocol_name = self.protocol_name_for(&request);
+
 				match e {
 					RequestFailure::Network(OutboundFailure::Timeout) => {
 						self.network_service.report_peer(peer_id, rep::TIMEOUT);
-						self.network_service
-							.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
+						self.network_service.disconnect_peer(peer_id, protocol_name);
+						if let Some(metrics) = &self.metrics {
+							metrics.timeout.inc();
+						}
 					},
 					RequestFailure::Network(OutboundFailure::UnsupportedProtocols) => {
 						self.network_service.report_peer(peer_id, rep::BAD_PROTOCOL);
-						self.network_service
-							.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
+						self.network_service.disconnect_peer(peer_id, protocol_name);
+						if let Some(metrics) = &self.metrics {
+							metrics.bad_protocol.inc();
+						}
 					},
 					RequestFailure::Network(OutboundFailure::DialFailure) => {
-						self.network_service
-							.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
+						self.network_service.disconnect_peer(peer_id, protocol_name);
 					},
 					RequestFailure::Refused => {
 						self.network_service.report_peer(peer_id, rep::REFUSED);
-						self.network_service
-							.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
+						self.network_service.disconnect_peer(peer_id, protocol_name);
+						if let Some(metrics) = &self.metrics {
+							metrics.refused.inc();
+						}
 					},
 					RequestFailure::Network(OutboundFailure::ConnectionClosed) |
 					RequestFailure::NotConnected => {
-						self.network_service
-							.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
+						self.network_service.disconnect_peer(peer_id, protocol_name);
 					},
 					RequestFailure::UnknownProtocol => {
 						debug_assert!(false, "Block request protocol should always be known.");
@@ -358,9 +942,141 @@ where
 					target: LOG_TARGET,
 					"Request to peer {peer_id:?} failed due to oneshot being canceled.",
 				);
-				self.network_service
-					.disconnect_peer(peer_id, self.state_request_protocol_name.clone());
+				self.on_request_failed(&request);
+				self.network_service.disconnect_peer(peer_id, self.protocol_name_for(&request));
+			},
+		}
+	}
+
+	/// Reset any bookkeeping tied to a request that just failed, so it gets retried instead of
+	/// wedging the engine.
+	///
+	/// In particular, a failed [`PeerRequest::WarpProof`] must clear `warp_in_flight` - it's
+	/// otherwise only ever reset on the success path, and `ensure_warp_request_in_flight` refuses
+	/// to send another proof request while it's still `true`. `next_request` is left untouched,
+	/// so the very next tick's `ensure_warp_request_in_flight` call re-sends the same request to
+	/// whichever peer is available.
+	fn on_request_failed(&mut self, request: &PeerRequest) {
+		if matches!(request, PeerRequest::WarpProof) {
+			self.warp_in_flight = false;
+		}
+	}
+
+	/// Protocol name a given request kind was sent on, for reporting/disconnecting the peer it
+	/// just failed with.
+	fn protocol_name_for(&self, request: &PeerRequest) -> ProtocolName {
+		protocol_name_for(request, &self.state_request_protocol_name, &self.warp_request_protocol_name)
+	}
+}
+
+/// Pick the best peer to retry a request on, excluding `exclude` and any peer for which `busy`
+/// returns `true`, preferring the one that has announced the highest best block.
+///
+/// Factored out of [`FastSyncingEngine::best_alternative_peer`] so the selection rule itself can
+/// be exercised without an entire engine.
+fn pick_best_peer<B: BlockT>(
+	peers: &HashMap<PeerId, Peer<B>>,
+	exclude: PeerId,
+	busy: impl Fn(&PeerId) -> bool,
+) -> Option<PeerId> {
+	peers
+		.iter()
+		.filter(|(peer_id, _)| **peer_id != exclude && !busy(peer_id))
+		.max_by_key(|(_, peer)| peer.info.best_number)
+		.map(|(peer_id, _)| *peer_id)
+}
+
+/// Protocol name a given request kind was sent on.
+///
+/// Factored out of [`FastSyncingEngine::protocol_name_for`] so the kind-to-protocol mapping can
+/// be exercised without an entire engine.
+fn protocol_name_for(
+	request: &PeerRequest,
+	state_request_protocol_name: &ProtocolName,
+	warp_request_protocol_name: &Option<ProtocolName>,
+) -> ProtocolName {
+	match request {
+		PeerRequest::WarpProof => warp_request_protocol_name
+			.clone()
+			.expect("only reached for a request that was actually sent on this protocol; qed"),
+		PeerRequest::State | PeerRequest::Block(_) => state_request_protocol_name.clone(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::testing::{Block as TestBlock, ExtrinsicWrapper};
+	use std::num::NonZeroUsize;
+
+	type Block = TestBlock<ExtrinsicWrapper<u64>>;
+
+	fn peer(best_number: u64) -> Peer<Block> {
+		Peer {
+			info: ExtendedPeerInfo {
+				roles: Default::default(),
+				best_hash: Default::default(),
+				best_number,
 			},
+			known_blocks: LruHashSet::new(NonZeroUsize::new(1).unwrap()),
 		}
 	}
+
+	#[test]
+	fn picks_the_peer_with_the_highest_best_number() {
+		let a = PeerId::random();
+		let b = PeerId::random();
+		let peers = HashMap::from([(a, peer(10)), (b, peer(20))]);
+
+		assert_eq!(pick_best_peer(&peers, PeerId::random(), |_| false), Some(b));
+	}
+
+	#[test]
+	fn excludes_the_peer_that_just_timed_out() {
+		let a = PeerId::random();
+		let b = PeerId::random();
+		let peers = HashMap::from([(a, peer(10)), (b, peer(20))]);
+
+		// `b` has the higher best number but just timed out, so `a` is picked instead.
+		assert_eq!(pick_best_peer(&peers, b, |_| false), Some(a));
+	}
+
+	#[test]
+	fn excludes_peers_with_a_request_already_in_flight() {
+		let a = PeerId::random();
+		let b = PeerId::random();
+		let peers = HashMap::from([(a, peer(10)), (b, peer(20))]);
+
+		// `b` has the higher best number but is already busy, so `a` is picked instead.
+		assert_eq!(pick_best_peer(&peers, PeerId::random(), |peer_id| *peer_id == b), Some(a));
+	}
+
+	#[test]
+	fn returns_none_when_every_peer_is_excluded_or_busy() {
+		let a = PeerId::random();
+		let peers = HashMap::from([(a, peer(10))]);
+
+		assert_eq!(pick_best_peer(&peers, a, |_| false), None);
+		assert_eq!(pick_best_peer(&peers, PeerId::random(), |_| true), None);
+	}
+
+	/// The bug this pins down: a failed `PeerRequest::WarpProof` must disconnect on
+	/// `warp_request_protocol_name`, not `state_request_protocol_name` - getting this wrong was
+	/// harmless for reporting purposes but masked `warp_in_flight` never being reset on this
+	/// path (see `on_request_failed`).
+	#[test]
+	fn warp_proof_failure_uses_the_warp_protocol() {
+		let state: ProtocolName = "/state".into();
+		let warp: ProtocolName = "/warp".into();
+
+		assert_eq!(protocol_name_for(&PeerRequest::WarpProof, &state, &Some(warp.clone())), warp);
+	}
+
+	#[test]
+	fn state_failure_uses_the_state_protocol() {
+		let state: ProtocolName = "/state".into();
+		let warp: ProtocolName = "/warp".into();
+
+		assert_eq!(protocol_name_for(&PeerRequest::State, &state, &Some(warp)), state);
+	}
 }
@@ -0,0 +1,140 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! HTTP bootstrap for [`FastSyncingEngine`](crate::fast_sync_engine::FastSyncingEngine).
+//!
+//! Lets a node discover its own fast-sync target (finalized header, justifications, and a
+//! starting peer set) from a trusted node's HTTP endpoint, instead of requiring the caller to
+//! already know it.
+
+use codec::Decode;
+use crate::LOG_TARGET;
+use libp2p::PeerId;
+use sp_blockchain::Error as ClientError;
+use sp_runtime::{traits::Block as BlockT, Justifications};
+
+/// The finalized checkpoint advertised by a trusted bootstrap node.
+pub struct RemoteCheckpoint<B: BlockT> {
+	pub header: B::Header,
+	pub body: Option<Vec<B::Extrinsic>>,
+	pub justifications: Justifications,
+	/// Peers the bootstrap node suggests we seed our sync peer pool with.
+	pub peers: Vec<PeerId>,
+}
+
+/// Verifies a finalized justification against a known authority set.
+///
+/// Implemented by the caller so [`Bootstrapper`] doesn't need to know which consensus engine
+/// (GRANDPA, BEEFY, ...) produced the justification.
+pub trait JustificationVerifier<B: BlockT> {
+	fn verify(
+		&self,
+		header: &B::Header,
+		justifications: &Justifications,
+	) -> Result<(), ClientError>;
+}
+
+#[derive(serde::Deserialize)]
+struct RawCheckpoint {
+	header: String,
+	body: Option<String>,
+	justifications: String,
+	peers: Vec<String>,
+}
+
+/// Fetches a finalized checkpoint from a trusted node's HTTP endpoint.
+pub struct Bootstrapper {
+	client: reqwest::Client,
+	base_url: String,
+}
+
+impl Bootstrapper {
+	pub fn new(base_url: impl Into<String>) -> Self {
+		Self { client: reqwest::Client::new(), base_url: base_url.into() }
+	}
+
+	/// Fetch the latest finalized header, its justifications, and (optionally) its body from
+	/// `{base_url}/sync/v1/checkpoint`.
+	///
+	/// The fetched justifications are verified with `verifier` before this returns; a
+	/// checkpoint that fails verification is rejected rather than handed back to the caller.
+	pub async fn fetch_checkpoint<B: BlockT>(
+		&self,
+		with_body: bool,
+		verifier: &dyn JustificationVerifier<B>,
+	) -> Result<RemoteCheckpoint<B>, ClientError> {
+		let url = format!("{}/sync/v1/checkpoint?with_body={with_body}", self.base_url);
+
+		let raw: RawCheckpoint = self
+			.client
+			.get(&url)
+			.send()
+			.await
+			.map_err(|e| ClientError::Backend(format!("Bootstrap request to {url} failed: {e}")))?
+			.json()
+			.await
+			.map_err(|e| {
+				ClientError::Backend(format!("Bootstrap response from {url} was malformed: {e}"))
+			})?;
+
+		let header = decode_hex::<B::Header>(&raw.header, "header")?;
+		let body = raw
+			.body
+			.as_deref()
+			.map(|encoded| decode_hex::<Vec<B::Extrinsic>>(encoded, "body"))
+			.transpose()?;
+		let justifications = decode_hex::<Justifications>(&raw.justifications, "justifications")?;
+
+		verifier.verify(&header, &justifications).map_err(|e| {
+			log::warn!(
+				target: LOG_TARGET,
+				"Rejecting bootstrap checkpoint from {}: justification did not verify: {e:?}",
+				self.base_url,
+			);
+			e
+		})?;
+
+		let peers = raw
+			.peers
+			.iter()
+			.map(|peer| {
+				peer.parse::<PeerId>().map_err(|e| {
+					ClientError::Backend(format!(
+						"Bootstrap node returned an invalid peer id {peer:?}: {e}"
+					))
+				})
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		if peers.is_empty() {
+			return Err(ClientError::Backend(format!(
+				"Bootstrap node {} did not advertise any sync peers",
+				self.base_url,
+			)))
+		}
+
+		Ok(RemoteCheckpoint { header, body, justifications, peers })
+	}
+}
+
+fn decode_hex<T: Decode>(data: &str, what: &str) -> Result<T, ClientError> {
+	let bytes = array_bytes::hex2bytes(data)
+		.map_err(|e| ClientError::Backend(format!("Bootstrap {what} was not valid hex: {e:?}")))?;
+	T::decode(&mut &bytes[..])
+		.map_err(|e| ClientError::Backend(format!("Bootstrap {what} failed to decode: {e}")))
+}
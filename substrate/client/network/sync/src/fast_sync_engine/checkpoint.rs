@@ -0,0 +1,161 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk checkpointing for resumable state sync.
+//!
+//! A fast sync of a large chain can take a long time, and without this a restart throws away
+//! all downloaded state. A checkpoint only ever advances after the corresponding
+//! `StateResponse` has been validated by the strategy, so a crash mid-request can never make a
+//! resumed sync skip an unverified key range.
+
+use codec::{Decode, Encode};
+use sp_blockchain::Error as ClientError;
+use sp_runtime::traits::Block as BlockT;
+use std::path::PathBuf;
+
+/// Progress recorded so a restarted sync can resume instead of starting from the genesis key.
+#[derive(Encode, Decode)]
+pub struct Checkpoint<B: BlockT> {
+	/// Header sync is targeting. A stored checkpoint is only honored if it was recorded for
+	/// this same target; otherwise the target has moved on and the checkpoint is stale.
+	pub target_header: B::Header,
+	/// The last key a fully accepted `StateResponse` batch covered. Resumed sync starts from
+	/// here rather than from the genesis key.
+	pub last_key: Vec<u8>,
+}
+
+/// A pluggable place to persist and retrieve [`Checkpoint`]s.
+pub trait CheckpointStore<B: BlockT>: Send + Sync {
+	/// Load the most recently saved checkpoint, if any.
+	fn load(&self) -> Result<Option<Checkpoint<B>>, ClientError>;
+	/// Persist `checkpoint`, overwriting whatever was previously saved.
+	fn save(&self, checkpoint: &Checkpoint<B>) -> Result<(), ClientError>;
+	/// Remove any saved checkpoint, e.g. once sync has finished importing.
+	fn clear(&self) -> Result<(), ClientError>;
+}
+
+/// Persists a single [`Checkpoint`] as a SCALE-encoded file.
+pub struct FileCheckpointStore {
+	path: PathBuf,
+}
+
+impl FileCheckpointStore {
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self { path: path.into() }
+	}
+}
+
+impl<B: BlockT> CheckpointStore<B> for FileCheckpointStore {
+	fn load(&self) -> Result<Option<Checkpoint<B>>, ClientError> {
+		match std::fs::read(&self.path) {
+			Ok(bytes) => Checkpoint::decode(&mut &bytes[..])
+				.map(Some)
+				.map_err(|e| ClientError::Backend(format!("Corrupt fast sync checkpoint: {e}"))),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+			Err(e) => Err(ClientError::Backend(format!(
+				"Failed to read fast sync checkpoint at {}: {e}",
+				self.path.display(),
+			))),
+		}
+	}
+
+	fn save(&self, checkpoint: &Checkpoint<B>) -> Result<(), ClientError> {
+		std::fs::write(&self.path, checkpoint.encode()).map_err(|e| {
+			ClientError::Backend(format!(
+				"Failed to write fast sync checkpoint to {}: {e}",
+				self.path.display(),
+			))
+		})
+	}
+
+	fn clear(&self) -> Result<(), ClientError> {
+		match std::fs::remove_file(&self.path) {
+			Ok(()) => Ok(()),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+			Err(e) => Err(ClientError::Backend(format!(
+				"Failed to clear fast sync checkpoint at {}: {e}",
+				self.path.display(),
+			))),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::testing::{Block as TestBlock, ExtrinsicWrapper, Header};
+
+	type Block = TestBlock<ExtrinsicWrapper<u64>>;
+
+	fn checkpoint(last_key: Vec<u8>) -> Checkpoint<Block> {
+		Checkpoint { target_header: Header::new_from_number(1), last_key }
+	}
+
+	fn store() -> (FileCheckpointStore, tempfile::TempDir) {
+		let dir = tempfile::tempdir().expect("failed to create temp dir");
+		let store = FileCheckpointStore::new(dir.path().join("fast-sync-checkpoint"));
+		(store, dir)
+	}
+
+	#[test]
+	fn load_returns_none_when_nothing_was_ever_saved() {
+		let (store, _dir) = store();
+
+		assert!(CheckpointStore::<Block>::load(&store).unwrap().is_none());
+	}
+
+	#[test]
+	fn save_then_load_round_trips_the_last_key() {
+		let (store, _dir) = store();
+		let checkpoint = checkpoint(vec![1, 2, 3]);
+
+		store.save(&checkpoint).unwrap();
+		let loaded = CheckpointStore::<Block>::load(&store).unwrap().expect("checkpoint was saved");
+
+		assert_eq!(loaded.target_header, checkpoint.target_header);
+		assert_eq!(loaded.last_key, checkpoint.last_key);
+	}
+
+	#[test]
+	fn save_overwrites_the_previous_checkpoint_rather_than_appending() {
+		let (store, _dir) = store();
+
+		store.save(&checkpoint(vec![1])).unwrap();
+		store.save(&checkpoint(vec![2])).unwrap();
+
+		let loaded = CheckpointStore::<Block>::load(&store).unwrap().expect("checkpoint was saved");
+		assert_eq!(loaded.last_key, vec![2]);
+	}
+
+	#[test]
+	fn clear_removes_a_saved_checkpoint() {
+		let (store, _dir) = store();
+		store.save(&checkpoint(vec![1])).unwrap();
+
+		store.clear().unwrap();
+
+		assert!(CheckpointStore::<Block>::load(&store).unwrap().is_none());
+	}
+
+	#[test]
+	fn clear_is_a_no_op_when_nothing_was_saved() {
+		let (store, _dir) = store();
+
+		store.clear().unwrap();
+	}
+}
@@ -0,0 +1,142 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Prometheus metrics for [`FastSyncingEngine`](crate::fast_sync_engine::FastSyncingEngine).
+
+use substrate_prometheus_endpoint::{
+	register, Counter, Gauge, Histogram, HistogramOpts, PrometheusError, Registry, U64,
+};
+
+/// Prometheus metrics tracking fast-sync progress.
+///
+/// Registered once, against the registry passed into
+/// [`FastSyncingEngine::new`](crate::fast_sync_engine::FastSyncingEngine::new), so operators can
+/// watch a long state sync make progress instead of guessing from logs.
+#[derive(Clone)]
+pub struct Metrics {
+	/// Number of state responses received and successfully decoded.
+	pub state_responses_received: Counter<U64>,
+	/// Number of `BAD_MESSAGE` reputation events.
+	pub bad_message: Counter<U64>,
+	/// Number of `BAD_PROTOCOL` reputation events.
+	pub bad_protocol: Counter<U64>,
+	/// Number of `REFUSED` reputation events.
+	pub refused: Counter<U64>,
+	/// Number of `TIMEOUT` reputation events, libp2p- or locally-detected alike.
+	pub timeout: Counter<U64>,
+	/// Peers dropped via `StateStrategyAction::DropPeer`.
+	pub peers_dropped: Counter<U64>,
+	/// Cumulative bytes of successfully decoded state responses, counted before the strategy
+	/// accepts or rejects them.
+	pub bytes_imported: Counter<U64>,
+	/// Cumulative key-value pairs in successfully decoded state responses, counted before the
+	/// strategy accepts or rejects them.
+	pub key_values_imported: Counter<U64>,
+	/// Number of peers currently connected.
+	pub connected_peers: Gauge<U64>,
+	/// Total time elapsed since fast sync started, in seconds.
+	pub sync_duration_seconds: Gauge<U64>,
+	/// Round-trip latency of state requests, from `send_state_request` to the matching
+	/// `ResponseEvent`.
+	pub request_duration: Histogram,
+}
+
+impl Metrics {
+	/// Register the metrics against `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			state_responses_received: register(
+				Counter::new(
+					"substrate_sync_fast_state_responses_received_total",
+					"Number of state responses received and successfully decoded",
+				)?,
+				registry,
+			)?,
+			bad_message: register(
+				Counter::new(
+					"substrate_sync_fast_bad_message_total",
+					"Number of BAD_MESSAGE reputation events",
+				)?,
+				registry,
+			)?,
+			bad_protocol: register(
+				Counter::new(
+					"substrate_sync_fast_bad_protocol_total",
+					"Number of BAD_PROTOCOL reputation events",
+				)?,
+				registry,
+			)?,
+			refused: register(
+				Counter::new(
+					"substrate_sync_fast_refused_total",
+					"Number of REFUSED reputation events",
+				)?,
+				registry,
+			)?,
+			timeout: register(
+				Counter::new(
+					"substrate_sync_fast_timeout_total",
+					"Number of TIMEOUT reputation events",
+				)?,
+				registry,
+			)?,
+			peers_dropped: register(
+				Counter::new(
+					"substrate_sync_fast_peers_dropped_total",
+					"Number of peers dropped during fast sync",
+				)?,
+				registry,
+			)?,
+			bytes_imported: register(
+				Counter::new(
+					"substrate_sync_fast_bytes_imported_total",
+					"Cumulative bytes of successfully decoded state responses",
+				)?,
+				registry,
+			)?,
+			key_values_imported: register(
+				Counter::new(
+					"substrate_sync_fast_key_values_imported_total",
+					"Cumulative key-value pairs in successfully decoded state responses",
+				)?,
+				registry,
+			)?,
+			connected_peers: register(
+				Gauge::new(
+					"substrate_sync_fast_connected_peers",
+					"Number of peers currently connected",
+				)?,
+				registry,
+			)?,
+			sync_duration_seconds: register(
+				Gauge::new(
+					"substrate_sync_fast_sync_duration_seconds",
+					"Total time elapsed since fast sync started",
+				)?,
+				registry,
+			)?,
+			request_duration: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"substrate_sync_fast_request_duration_seconds",
+					"Round-trip latency of state requests",
+				))?,
+				registry,
+			)?,
+		})
+	}
+}
@@ -0,0 +1,55 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Warp-proof phase for
+//! [`FastSyncingEngine`](crate::fast_sync_engine::FastSyncingEngine).
+//!
+//! Lets a node establish its finalized `target_header` directly from the network instead of
+//! requiring the caller to already know it: a short chain of warp proofs is downloaded and
+//! verified, each advancing the known authority set, until the verifier reports the chain tip
+//! has been reached. The resulting header then feeds into the existing state-download phase
+//! exactly as a manually supplied target would.
+
+use codec::{Decode, Encode};
+use sp_blockchain::Error as ClientError;
+use sp_runtime::traits::Block as BlockT;
+
+/// Request for the next warp proof, covering the range starting at `begin`.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct WarpProofRequest<B: BlockT> {
+	/// Hash of the block to start the proof from.
+	pub begin: B::Hash,
+}
+
+/// The finalized header a warp proof establishes, and whether the chain tip has been reached.
+pub struct VerifiedWarpProof<B: BlockT> {
+	/// Finalized header the proof establishes.
+	pub header: B::Header,
+	/// Whether this proof reached the chain tip, or whether another one should be requested
+	/// starting from `header`.
+	pub is_finished: bool,
+}
+
+/// Verifies a raw warp proof and reports the finalized header it establishes.
+///
+/// Implemented by the caller so the engine doesn't need to know which consensus engine
+/// (GRANDPA, BEEFY, ...) produced the proof, mirroring
+/// [`JustificationVerifier`](crate::fast_sync_engine::bootstrap::JustificationVerifier).
+pub trait WarpProofVerifier<B: BlockT>: Send + Sync {
+	fn verify(&self, proof: &[u8]) -> Result<VerifiedWarpProof<B>, ClientError>;
+}